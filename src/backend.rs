@@ -0,0 +1,200 @@
+use std::path::Path;
+
+use goblin::error;
+
+/// Which object format a [`NormalizedBinary`] was parsed from. The traversal in `main` branches on
+/// this wherever the two formats' load-time search semantics genuinely differ (dylib-style
+/// `@rpath`/`@loader_path` vs ELF's `$ORIGIN`/`DT_RUNPATH`); everything else (depth limiting,
+/// visited tracking, ignore prefixes, printing) is shared.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BinaryFormat {
+    MachO,
+    Elf,
+}
+
+/// A single dependency entry, normalized across formats. `compatibility_version` only ever comes
+/// from Mach-O's `LC_LOAD_DYLIB`; ELF's `DT_NEEDED` carries no version requirement.
+pub struct NeededLib {
+    pub name: String,
+    pub compatibility_version: Option<u32>,
+}
+
+/// A load-time search path entry in the binary's own token syntax (Mach-O's `@rpath`/
+/// `@executable_path`/`@loader_path`, or ELF's `$ORIGIN`/`$LIB`/`$PLATFORM`), tagged with whether
+/// it's searched before or after the recorded dependency names. Mach-O rpaths and ELF's
+/// `DT_RPATH` are searched first; ELF's `DT_RUNPATH` is only consulted after the recorded
+/// dependency name itself has failed (and before the standard system library dirs).
+pub struct SearchPath {
+    pub raw: String,
+    pub searched_after_needed: bool,
+}
+
+/// The shape `get_or_parse` needs out of any supported binary format.
+pub struct NormalizedBinary {
+    pub format: BinaryFormat,
+    pub id_name: Option<String>,
+    pub current_version: Option<u32>,
+    pub needed: Vec<NeededLib>,
+    pub search_paths: Vec<SearchPath>,
+}
+
+/// A backend parses one object format's dependency metadata into the shared [`NormalizedBinary`]
+/// shape, so the traversal doesn't need to know the format beyond picking a backend and (for the
+/// few places load-time search semantics differ) checking `NormalizedBinary::format`.
+pub trait Backend {
+    fn normalize(path: &Path, buffer: &[u8]) -> Result<NormalizedBinary, error::Error>;
+}
+
+// `binary.libs` always starts with a "self" placeholder (goblin's mach parser seeds it as
+// `vec!["self"]`, only overwritten in place by `LC_ID_DYLIB`) that isn't a real dependency and has
+// no compatibility_version of its own. `compatibility_versions` holds one entry per actual
+// `LC_LOAD_DYLIB`-family command in the same order those commands fed the rest of `libs`, so pair
+// it against everything *after* that placeholder instead of the whole list -- a positional zip
+// against the whole list silently shifts by one on any binary without `LC_ID_DYLIB`, i.e. every
+// plain executable.
+fn align_needed_libs(libs: &[&str], compatibility_versions: &[u32]) -> Vec<NeededLib> {
+    let mut needed = Vec::with_capacity(libs.len());
+
+    if let Some(self_name) = libs.first() {
+        needed.push(NeededLib {
+            name: self_name.to_string(),
+            compatibility_version: None,
+        });
+    }
+
+    needed.extend(
+        libs.iter()
+            .skip(1)
+            .zip(compatibility_versions.iter().copied())
+            .map(|(name, compatibility_version)| NeededLib {
+                name: name.to_string(),
+                compatibility_version: Some(compatibility_version),
+            }),
+    );
+
+    needed
+}
+
+pub struct MachOBackend;
+
+impl Backend for MachOBackend {
+    // Mach-O parsing itself is untouched from before this trait existed (see `load_macho` in
+    // main.rs) -- this just reshapes its output into the normalized struct.
+    fn normalize(path: &Path, buffer: &[u8]) -> Result<NormalizedBinary, error::Error> {
+        let binary = crate::load_macho(path, buffer)?;
+
+        let self_name = binary.libs.first().copied();
+        let compatibility_versions = crate::dylib_compatibility_versions(&binary);
+        let needed = align_needed_libs(&binary.libs, &compatibility_versions);
+
+        let search_paths = binary
+            .rpaths
+            .iter()
+            .map(|rpath| SearchPath {
+                raw: rpath.to_string(),
+                searched_after_needed: false,
+            })
+            .collect();
+
+        Ok(NormalizedBinary {
+            format: BinaryFormat::MachO,
+            // The "self" placeholder only becomes a real id once `LC_ID_DYLIB` overwrites it; a
+            // plain executable has no such command, so it stays literally "self" and isn't an id.
+            id_name: self_name.filter(|n| *n != "self").map(|s| s.to_string()),
+            current_version: crate::dylib_current_version(&binary),
+            needed,
+            search_paths,
+        })
+    }
+}
+
+pub struct ElfBackend;
+
+impl Backend for ElfBackend {
+    fn normalize(_path: &Path, buffer: &[u8]) -> Result<NormalizedBinary, error::Error> {
+        let elf = goblin::elf::Elf::parse(buffer)?;
+
+        let needed = elf
+            .libraries
+            .iter()
+            .map(|name| NeededLib {
+                name: name.to_string(),
+                compatibility_version: None,
+            })
+            .collect();
+
+        // The real loader ignores DT_RPATH entirely when DT_RUNPATH is also present, so don't even
+        // offer it as a candidate in that case -- searching it anyway can report a path found that
+        // ld.so would never have considered.
+        let mut search_paths = vec![];
+        if elf.runpaths.is_empty() {
+            for rpath in &elf.rpaths {
+                search_paths.push(SearchPath {
+                    raw: rpath.to_string(),
+                    searched_after_needed: false,
+                });
+            }
+        }
+        for runpath in &elf.runpaths {
+            search_paths.push(SearchPath {
+                raw: runpath.to_string(),
+                searched_after_needed: true,
+            });
+        }
+
+        Ok(NormalizedBinary {
+            format: BinaryFormat::Elf,
+            id_name: elf.soname.map(|s| s.to_string()),
+            current_version: None,
+            needed,
+            search_paths,
+        })
+    }
+}
+
+#[cfg(test)]
+mod align_needed_libs_tests {
+    use super::*;
+
+    #[test]
+    fn aligns_versions_with_dependencies_when_self_has_no_id_dylib() {
+        // Regression test for the executable case: no LC_ID_DYLIB means
+        // `compatibility_versions` has one fewer entry than `libs`, and a naive positional zip
+        // over the whole list (as originally shipped in ce9f3dd) shifts every dependency's
+        // version by one and drops the last dependency's entirely.
+        let libs = ["self", "libfoo.dylib", "libbar.dylib"];
+        let compatibility_versions = [0x0001_0000, 0x0002_0000];
+
+        let needed = align_needed_libs(&libs, &compatibility_versions);
+
+        assert_eq!(needed.len(), 3);
+        assert_eq!(needed[0].name, "self");
+        assert_eq!(needed[0].compatibility_version, None);
+        assert_eq!(needed[1].name, "libfoo.dylib");
+        assert_eq!(needed[1].compatibility_version, Some(0x0001_0000));
+        assert_eq!(needed[2].name, "libbar.dylib");
+        assert_eq!(needed[2].compatibility_version, Some(0x0002_0000));
+    }
+
+    #[test]
+    fn aligns_versions_with_dependencies_when_self_has_an_id_dylib() {
+        // With LC_ID_DYLIB present, "self" is overwritten by the library's own install name but
+        // still occupies libs[0]; `dylib_compatibility_versions` excludes LC_ID_DYLIB, so the
+        // counts still line up the same way as the no-id case.
+        let libs = ["libself.dylib", "libfoo.dylib"];
+        let compatibility_versions = [0x0001_0000];
+
+        let needed = align_needed_libs(&libs, &compatibility_versions);
+
+        assert_eq!(needed.len(), 2);
+        assert_eq!(needed[0].name, "libself.dylib");
+        assert_eq!(needed[0].compatibility_version, None);
+        assert_eq!(needed[1].name, "libfoo.dylib");
+        assert_eq!(needed[1].compatibility_version, Some(0x0001_0000));
+    }
+
+    #[test]
+    fn empty_libs_produce_no_needed_entries() {
+        assert!(align_needed_libs(&[], &[]).is_empty());
+    }
+}
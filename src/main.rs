@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fs;
 use std::path::Path;
@@ -5,12 +6,103 @@ use std::path::PathBuf;
 
 use goblin::{error, Object};
 
+use backend::{Backend, BinaryFormat, SearchPath};
+use output::TreeNode;
+
+mod backend;
 mod cli;
 mod extract;
+mod output;
 #[macro_use]
 mod util;
 
-fn load_binary<'a>(path: &Path, buffer: &'a [u8]) -> Result<goblin::mach::MachO<'a>, error::Error> {
+// A parsed binary's shape, stripped of its borrow on the raw file bytes so it can outlive the
+// buffer it was parsed from and be memoized across the whole traversal.
+struct ParsedDylib {
+    format: BinaryFormat,
+    libs: Vec<String>,
+    search_paths: Vec<SearchPath>,
+    compatibility_versions: Vec<u32>,
+    current_version: Option<u32>,
+    id_name: Option<String>,
+}
+
+// The cache key for a visited path: its canonical form, so two different spellings of the same
+// file (a symlink and its target, a path with a `./` component) hit the same cache entry. Falls
+// back to the path as given when canonicalization fails (e.g. it doesn't exist) so a dangling
+// dependency still gets a stable, usable key instead of panicking.
+fn cache_key(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+// Process-wide memoization of parsed dylibs, keyed by canonical path. Diamond-shaped dependency
+// graphs revisit the same system dylibs at every fan-in, so without this we'd re-read and
+// re-parse them once per edge instead of once per node.
+struct ParseCache {
+    entries: HashMap<PathBuf, ParsedDylib>,
+}
+
+impl ParseCache {
+    fn new() -> Self {
+        ParseCache {
+            entries: HashMap::new(),
+        }
+    }
+
+    fn get_or_parse(&mut self, path: &Path) -> Result<&ParsedDylib, error::Error> {
+        let canonical = cache_key(path);
+        if !self.entries.contains_key(&canonical) {
+            let buffer = fs::read(path)?;
+            let normalized = normalize_binary(path, &buffer)?;
+            let parsed = ParsedDylib {
+                format: normalized.format,
+                libs: normalized.needed.iter().map(|n| n.name.clone()).collect(),
+                compatibility_versions: normalized
+                    .needed
+                    .iter()
+                    .map(|n| n.compatibility_version.unwrap_or(0))
+                    .collect(),
+                search_paths: normalized.search_paths,
+                current_version: normalized.current_version,
+                id_name: normalized.id_name,
+            };
+            self.entries.insert(canonical.clone(), parsed);
+        }
+
+        Ok(self.entries.get(&canonical).unwrap())
+    }
+}
+
+// Dispatches to the backend for whichever object format `buffer` turns out to be. Mach-O parsing
+// itself (`load_macho`) is unchanged from before backends existed; this just picks it, or the new
+// ELF backend, based on `goblin::Object`.
+fn normalize_binary(path: &Path, buffer: &[u8]) -> Result<backend::NormalizedBinary, error::Error> {
+    match Object::parse(buffer)? {
+        Object::Mach(_) => backend::MachOBackend::normalize(path, buffer),
+        Object::Elf(_) => backend::ElfBackend::normalize(path, buffer),
+        Object::Archive(_) => {
+            failf!(
+                "{}: error: archives are not currently supported",
+                path.to_string_lossy(),
+            );
+        }
+        Object::PE(_) => {
+            failf!(
+                "{}: error: PE binaries are not currently supported",
+                path.to_string_lossy(),
+            );
+        }
+        Object::Unknown(magic) => {
+            failf!(
+                "{}: error: unknown file magic: {:#x}, please file an issue if this is a Mach-O or ELF file",
+                path.to_string_lossy(),
+                magic,
+            );
+        }
+    }
+}
+
+pub(crate) fn load_macho<'a>(path: &Path, buffer: &'a [u8]) -> Result<goblin::mach::MachO<'a>, error::Error> {
     match Object::parse(buffer)? {
         Object::Mach(mach) => match mach {
             goblin::mach::Mach::Fat(fat) => {
@@ -65,22 +157,96 @@ fn versioned_path(prefix: Option<PathBuf>, lib: &str, version: &str) -> PathBuf
     Path::new(framework_with_version.as_ref()).to_path_buf()
 }
 
+// The search paths dyld itself consults before falling back to the recorded install name.
+// `*_FRAMEWORK_PATH` / `*_LIBRARY_PATH` are tried ahead of the install name; the `*_FALLBACK_*`
+// variants are only tried after every other candidate for a given lib has failed to exist.
+struct DyldSearchPaths {
+    framework_path: Vec<PathBuf>,
+    library_path: Vec<PathBuf>,
+    fallback_framework_path: Vec<PathBuf>,
+    fallback_library_path: Vec<PathBuf>,
+}
+
+impl DyldSearchPaths {
+    fn from_env() -> Self {
+        DyldSearchPaths {
+            framework_path: env_path_list("DYLD_FRAMEWORK_PATH"),
+            library_path: env_path_list("DYLD_LIBRARY_PATH"),
+            fallback_framework_path: env_path_list("DYLD_FALLBACK_FRAMEWORK_PATH"),
+            fallback_library_path: env_path_list("DYLD_FALLBACK_LIBRARY_PATH"),
+        }
+    }
+}
+
+fn env_path_list(name: &str) -> Vec<PathBuf> {
+    std::env::var(name)
+        .map(|value| {
+            value
+                .split(':')
+                .filter(|entry| !entry.is_empty())
+                .map(PathBuf::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// If `lib` names a framework (e.g. `/System/.../Foo.framework/Foo`), returns the
+// framework-relative suffix (`Foo.framework/Foo`) that `DYLD_FRAMEWORK_PATH` entries are joined
+// with.
+fn framework_suffix(lib: &str) -> Option<&str> {
+    let re = regex::Regex::new(r#"[^/]+\.framework/.*$"#).unwrap();
+    re.find(lib).map(|m| m.as_str())
+}
+
+// Resolves a `@executable_path/` or `@loader_path/` prefixed string against the two directories
+// dyld actually uses: `@executable_path` is always relative to the main executable (constant for
+// the whole walk), while `@loader_path` is relative to whichever Mach-O image contains the load
+// command referencing it (the node currently being visited).
+fn resolve_at_path(value: &str, main_executable_dir: &Path, loader_dir: &Path) -> Option<PathBuf> {
+    let (base, rest) = if let Some(rest) = value.strip_prefix("@executable_path/") {
+        (main_executable_dir, rest)
+    } else if let Some(rest) = value.strip_prefix("@loader_path/") {
+        (loader_dir, rest)
+    } else {
+        return None;
+    };
+
+    let mut path = base.to_path_buf();
+    path.push(rest);
+    Some(path)
+}
+
 fn get_potential_paths(
     shared_cache_root: &Option<PathBuf>,
-    executable_path: &Path,
+    dyld_search_paths: &DyldSearchPaths,
+    main_executable_path: &Path,
+    actual_path: &Path,
     lib: &str,
-    rpaths: &Vec<&str>,
+    rpaths: &[String],
 ) -> Vec<PathBuf> {
     let mut paths = vec![];
+    let main_executable_dir = main_executable_path.parent().unwrap();
+    let loader_dir = actual_path.parent().unwrap();
+
+    // DYLD_FRAMEWORK_PATH/DYLD_LIBRARY_PATH override by framework suffix or leaf filename ahead of
+    // every other candidate, regardless of whether the dependency is referenced via @rpath/,
+    // @executable_path/, @loader_path/, or a bare install name -- dyld applies these env overrides
+    // uniformly, and @rpath/ is how the overwhelming majority of bundled-app dylibs are linked.
+    let framework_suffix = framework_suffix(lib);
+    if let Some(suffix) = framework_suffix {
+        for dir in &dyld_search_paths.framework_path {
+            paths.push(dir.join(suffix));
+        }
+    } else if let Some(leaf) = Path::new(lib).file_name() {
+        for dir in &dyld_search_paths.library_path {
+            paths.push(dir.join(leaf));
+        }
+    }
 
     if lib.starts_with("@rpath/") {
         let lib = lib.split_once('/').unwrap().1;
         for rpath in rpaths {
-            // TODO: @loader_path/ isn't right here, but this is better than nothing for now
-            if rpath.starts_with("@executable_path/") || rpath.starts_with("@loader_path/") {
-                let rpath = rpath.split_once('/').unwrap().1;
-                let mut path = PathBuf::from(executable_path.parent().unwrap());
-                path.push(rpath);
+            if let Some(mut path) = resolve_at_path(rpath, main_executable_dir, loader_dir) {
                 path.push(lib);
                 paths.push(path);
                 continue;
@@ -99,40 +265,93 @@ fn get_potential_paths(
             }
         }
     } else {
-        paths.push(Path::new(lib).to_path_buf());
-        paths.push(versioned_path(None, lib, "A"));
-        paths.push(versioned_path(None, lib, "B"));
-        paths.push(versioned_path(None, lib, "C"));
-        paths.push(versioned_path(None, lib, "D"));
-
-        if let Some(shared_cache_root) = &shared_cache_root {
-            let mut path = PathBuf::from(shared_cache_root);
-            let stripped = lib.strip_prefix('/').unwrap();
-            path.push(stripped);
+        if let Some(path) = resolve_at_path(lib, main_executable_dir, loader_dir) {
             paths.push(path);
+        } else {
+            paths.push(Path::new(lib).to_path_buf());
+            paths.push(versioned_path(None, lib, "A"));
+            paths.push(versioned_path(None, lib, "B"));
+            paths.push(versioned_path(None, lib, "C"));
+            paths.push(versioned_path(None, lib, "D"));
 
-            paths.push(versioned_path(Some(shared_cache_root.to_owned()), lib, "A"));
-            paths.push(versioned_path(Some(shared_cache_root.to_owned()), lib, "B"));
-            paths.push(versioned_path(Some(shared_cache_root.to_owned()), lib, "C"));
-            paths.push(versioned_path(Some(shared_cache_root.to_owned()), lib, "D"));
+            if let Some(shared_cache_root) = &shared_cache_root {
+                let mut path = PathBuf::from(shared_cache_root);
+                let stripped = lib.strip_prefix('/').unwrap();
+                path.push(stripped);
+                paths.push(path);
 
-            let mut ios_support_root = PathBuf::from(shared_cache_root);
-            ios_support_root.push("System/iOSSupport");
-            ios_support_root.push(lib);
-            paths.push(ios_support_root);
+                paths.push(versioned_path(Some(shared_cache_root.to_owned()), lib, "A"));
+                paths.push(versioned_path(Some(shared_cache_root.to_owned()), lib, "B"));
+                paths.push(versioned_path(Some(shared_cache_root.to_owned()), lib, "C"));
+                paths.push(versioned_path(Some(shared_cache_root.to_owned()), lib, "D"));
+
+                let mut ios_support_root = PathBuf::from(shared_cache_root);
+                ios_support_root.push("System/iOSSupport");
+                ios_support_root.push(lib);
+                paths.push(ios_support_root);
+
+                let mut ios_support_root = PathBuf::from(shared_cache_root);
+                ios_support_root.push("System/iOSSupport");
+                paths.push(versioned_path(Some(ios_support_root.clone()), lib, "A"));
+                paths.push(versioned_path(Some(ios_support_root.clone()), lib, "B"));
+                paths.push(versioned_path(Some(ios_support_root.clone()), lib, "C"));
+                paths.push(versioned_path(Some(ios_support_root.clone()), lib, "D"));
+            }
+        }
+    }
 
-            let mut ios_support_root = PathBuf::from(shared_cache_root);
-            ios_support_root.push("System/iOSSupport");
-            paths.push(versioned_path(Some(ios_support_root.clone()), lib, "A"));
-            paths.push(versioned_path(Some(ios_support_root.clone()), lib, "B"));
-            paths.push(versioned_path(Some(ios_support_root.clone()), lib, "C"));
-            paths.push(versioned_path(Some(ios_support_root.clone()), lib, "D"));
+    // DYLD_FALLBACK_* paths are only consulted once every other candidate above has failed.
+    if let Some(suffix) = framework_suffix {
+        for dir in &dyld_search_paths.fallback_framework_path {
+            paths.push(dir.join(suffix));
+        }
+    } else if let Some(leaf) = Path::new(lib).file_name() {
+        for dir in &dyld_search_paths.fallback_library_path {
+            paths.push(dir.join(leaf));
         }
     }
 
     paths
 }
 
+// Mach-O packs dylib versions as a 16.8.8 fixed-point `u32`: major in the high 16 bits, minor and
+// patch in the two low bytes.
+fn format_dylib_version(version: u32) -> String {
+    format!("{}.{}.{}", version >> 16, (version >> 8) & 0xff, version & 0xff)
+}
+
+// `binary.libs` only exposes the flattened install-name strings, so to get at the
+// `compatibility_version` the importer actually linked against we have to walk the raw
+// `LC_LOAD_DYLIB`-family load commands ourselves. `LC_ID_DYLIB` is deliberately excluded: it
+// overwrites the "self" placeholder at `binary.libs[0]` in place rather than appending an entry,
+// so including it here would misalign this list against the dependencies in `binary.libs[1..]`
+// that it's meant to line up with.
+pub(crate) fn dylib_compatibility_versions(binary: &goblin::mach::MachO) -> Vec<u32> {
+    use goblin::mach::load_command::CommandVariant;
+
+    binary
+        .load_commands
+        .iter()
+        .filter_map(|lc| match &lc.command {
+            CommandVariant::LoadDylib(d)
+            | CommandVariant::LoadWeakDylib(d)
+            | CommandVariant::ReexportDylib(d)
+            | CommandVariant::LoadUpwardDylib(d)
+            | CommandVariant::LazyLoadDylib(d) => Some(d.dylib.compatibility_version),
+            _ => None,
+        })
+        .collect()
+}
+
+pub(crate) fn dylib_current_version(binary: &goblin::mach::MachO) -> Option<u32> {
+    use goblin::mach::load_command::CommandVariant;
+
+    binary.load_commands.iter().find_map(|lc| match &lc.command {
+        CommandVariant::IdDylib(d) => Some(d.dylib.current_version),
+        _ => None,
+    })
+}
+
 fn should_ignore(lib: &str, ignore_prefixes: &Vec<String>) -> bool {
     for prefix in ignore_prefixes {
         if lib.starts_with(prefix) {
@@ -143,18 +362,68 @@ fn should_ignore(lib: &str, ignore_prefixes: &Vec<String>) -> bool {
     false
 }
 
-fn is_system_dependency(lib: &str) -> bool {
-    for prefix in ["/usr/lib/", "/System", "@rpath/libswift"] {
-        if lib.starts_with(prefix) {
-            return true;
+fn is_system_dependency(format: BinaryFormat, lib: &str) -> bool {
+    let prefixes: &[&str] = match format {
+        BinaryFormat::MachO => &["/usr/lib/", "/System", "@rpath/libswift"],
+        BinaryFormat::Elf => &["/lib/", "/lib64/", "/usr/lib/", "/usr/lib64/", "linux-vdso.so"],
+    };
+
+    prefixes.iter().any(|prefix| lib.starts_with(prefix))
+}
+
+// Expands the `$ORIGIN`, `$LIB`, and `$PLATFORM` dynamic-string tokens ld.so supports in
+// `DT_RPATH`/`DT_RUNPATH` entries. `$ORIGIN` is the ELF equivalent of Mach-O's `@loader_path`: the
+// directory of the object that contains the reference, not the main executable.
+fn expand_elf_tokens(value: &str, loader_dir: &Path) -> String {
+    let loader_dir = loader_dir.to_string_lossy();
+    value
+        .replace("$ORIGIN", &loader_dir)
+        .replace("${ORIGIN}", &loader_dir)
+        .replace("$LIB", "lib")
+        .replace("${LIB}", "lib")
+        .replace("$PLATFORM", std::env::consts::ARCH)
+        .replace("${PLATFORM}", std::env::consts::ARCH)
+}
+
+// Mirrors `get_potential_paths`, but for ELF's `DT_NEEDED`/`DT_RPATH`/`DT_RUNPATH` search order:
+// `DT_RPATH` (when present and `DT_RUNPATH` is absent) is searched before the bare `DT_NEEDED`
+// name, `DT_RUNPATH` only after it has failed, and the standard `/lib`, `/usr/lib` locations
+// (what a minimal `ld.so.conf` would otherwise contribute) are tried last of all.
+fn get_potential_elf_paths(actual_path: &Path, lib: &str, search_paths: &[SearchPath]) -> Vec<PathBuf> {
+    let loader_dir = actual_path.parent().unwrap();
+    let mut before_needed = vec![];
+    let mut after_needed = vec![];
+
+    for search_path in search_paths {
+        let expanded = expand_elf_tokens(&search_path.raw, loader_dir);
+        for dir in expanded.split(':').filter(|entry| !entry.is_empty()) {
+            let mut path = PathBuf::from(dir);
+            path.push(lib);
+            if search_path.searched_after_needed {
+                after_needed.push(path);
+            } else {
+                before_needed.push(path);
+            }
         }
     }
 
-    false
+    let mut paths = before_needed;
+    paths.push(Path::new(lib).to_path_buf());
+    paths.extend(after_needed);
+
+    for dir in ["/lib", "/lib64", "/usr/lib", "/usr/lib64"] {
+        let mut path = PathBuf::from(dir);
+        path.push(lib);
+        paths.push(path);
+    }
+
+    paths
 }
 
 fn print_dylib_paths(
     shared_cache_root: &Option<PathBuf>,
+    dyld_search_paths: &DyldSearchPaths,
+    main_executable_path: &Path,
     actual_path: &Path,
     canonical_path: &str,
     depth: usize,
@@ -164,22 +433,38 @@ fn print_dylib_paths(
     exclude_all_duplicates: bool,
     include_system_dependencies: bool,
     verbose: bool,
+    cache: &mut ParseCache,
 ) -> Result<HashSet<String>, error::Error> {
-    let buffer = fs::read(actual_path)?;
-    let binary = load_binary(actual_path, &buffer)?;
-
     verbose_log!(verbose, "Visiting lib: {:?}", actual_path);
     let indent = depth * 2;
     println!("{}{}:", " ".repeat(indent), canonical_path);
     let prefix = " ".repeat(indent + 2);
+
+    let parsed = cache.get_or_parse(actual_path)?;
+    let format = parsed.format;
+    let libs = parsed.libs.clone();
+    let search_paths: Vec<SearchPath> = parsed
+        .search_paths
+        .iter()
+        .map(|sp| SearchPath {
+            raw: sp.raw.clone(),
+            searched_after_needed: sp.searched_after_needed,
+        })
+        .collect();
+    let compatibility_versions = parsed.compatibility_versions.clone();
+
     let mut visited = visited.clone();
-    for dylib in binary.libs {
+    for (index, dylib) in libs.iter().enumerate() {
+        let dylib = dylib.as_str();
+
         // The LC_ID_DYLIB load command is contained in this list, so we need to skip the current
         // dylib to not get stuck in an infinite loop
         if dylib == "self" || dylib == canonical_path {
             continue;
         }
 
+        let required_compatibility_version = compatibility_versions.get(index).copied().unwrap_or(0);
+
         if depth + 1 > max_depth {
             continue;
         }
@@ -189,7 +474,7 @@ fn print_dylib_paths(
             continue;
         }
 
-        if !include_system_dependencies && is_system_dependency(dylib) {
+        if !include_system_dependencies && is_system_dependency(format, dylib) {
             verbose_log!(verbose, "Ignoring system dependency: {}", dylib);
             continue;
         }
@@ -203,13 +488,59 @@ fn print_dylib_paths(
 
         visited.insert(dylib.to_owned());
 
+        let candidate_paths = match format {
+            BinaryFormat::MachO => {
+                let rpaths: Vec<String> = search_paths.iter().map(|sp| sp.raw.clone()).collect();
+                get_potential_paths(
+                    shared_cache_root,
+                    dyld_search_paths,
+                    main_executable_path,
+                    actual_path,
+                    dylib,
+                    &rpaths,
+                )
+            }
+            BinaryFormat::Elf => get_potential_elf_paths(actual_path, dylib, &search_paths),
+        };
+
         let mut found = false;
-        for path in get_potential_paths(shared_cache_root, actual_path, dylib, &binary.rpaths) {
+        for path in candidate_paths {
             verbose_log!(verbose, "Checking path: {:?}", path);
             if path.exists() {
                 verbose_log!(verbose, "Found path: {:?}", path);
+
+                let resolved = cache.get_or_parse(&path)?;
+                let resolved_current_version = resolved.current_version;
+                let resolved_id_name = resolved.id_name.clone();
+
+                // The id a resolved dylib/shared object advertises for itself (Mach-O's
+                // `LC_ID_DYLIB` install name, ELF's `DT_SONAME`) doesn't have to match the name it
+                // was looked up by -- e.g. a versioned `.so` symlink -- so log the mismatch rather
+                // than silently trusting the lookup name.
+                if let Some(id_name) = &resolved_id_name {
+                    if id_name != dylib {
+                        verbose_log!(verbose, "{} resolved to a binary whose id is {}", dylib, id_name);
+                    }
+                }
+
+                if required_compatibility_version > 0 {
+                    if let Some(current_version) = resolved_current_version {
+                        if current_version < required_compatibility_version {
+                            println!(
+                                "{}warning: found {} (current {}) but importer requires compatibility >= {}",
+                                prefix,
+                                dylib,
+                                format_dylib_version(current_version),
+                                format_dylib_version(required_compatibility_version),
+                            );
+                        }
+                    }
+                }
+
                 visited.extend(print_dylib_paths(
                     shared_cache_root,
+                    dyld_search_paths,
+                    main_executable_path,
                     &path,
                     dylib,
                     depth + 1,
@@ -219,6 +550,7 @@ fn print_dylib_paths(
                     exclude_all_duplicates,
                     include_system_dependencies,
                     verbose,
+                    cache,
                 )?);
                 found = true;
                 break;
@@ -233,6 +565,158 @@ fn print_dylib_paths(
     Ok(visited)
 }
 
+// Mirrors `print_dylib_paths`'s traversal (same depth limiting, ignore prefixes, duplicate and
+// system-dependency handling) but builds a `TreeNode` instead of printing, for the `--format
+// json`/`--format dot` output modes.
+fn build_dependency_tree(
+    shared_cache_root: &Option<PathBuf>,
+    dyld_search_paths: &DyldSearchPaths,
+    main_executable_path: &Path,
+    actual_path: &Path,
+    canonical_path: &str,
+    depth: usize,
+    max_depth: usize,
+    visited: &HashSet<String>,
+    ignore_prefixes: &Vec<String>,
+    exclude_all_duplicates: bool,
+    include_system_dependencies: bool,
+    cache: &mut ParseCache,
+) -> Result<(TreeNode, HashSet<String>), error::Error> {
+    let parsed = cache.get_or_parse(actual_path)?;
+    let format = parsed.format;
+    let libs = parsed.libs.clone();
+    let search_paths: Vec<SearchPath> = parsed
+        .search_paths
+        .iter()
+        .map(|sp| SearchPath {
+            raw: sp.raw.clone(),
+            searched_after_needed: sp.searched_after_needed,
+        })
+        .collect();
+    let compatibility_versions = parsed.compatibility_versions.clone();
+
+    let mut visited = visited.clone();
+    let mut children = vec![];
+
+    for (index, dylib) in libs.iter().enumerate() {
+        let dylib = dylib.as_str();
+
+        if dylib == "self" || dylib == canonical_path {
+            continue;
+        }
+
+        let required_compatibility_version = compatibility_versions.get(index).copied().unwrap_or(0);
+        let required_compatibility_version = if required_compatibility_version > 0 {
+            Some(required_compatibility_version)
+        } else {
+            None
+        };
+
+        if depth + 1 > max_depth {
+            continue;
+        }
+
+        if should_ignore(dylib, ignore_prefixes) {
+            continue;
+        }
+
+        let is_system = is_system_dependency(format, dylib);
+        if !include_system_dependencies && is_system {
+            continue;
+        }
+
+        if visited.contains(&dylib.to_owned()) {
+            if !exclude_all_duplicates {
+                children.push(TreeNode {
+                    name: dylib.to_string(),
+                    resolved_path: None,
+                    current_version: None,
+                    required_compatibility_version,
+                    is_system,
+                    not_found: false,
+                    duplicate: true,
+                    children: vec![],
+                });
+            }
+            continue;
+        }
+
+        visited.insert(dylib.to_owned());
+
+        let candidate_paths = match format {
+            BinaryFormat::MachO => {
+                let rpaths: Vec<String> = search_paths.iter().map(|sp| sp.raw.clone()).collect();
+                get_potential_paths(
+                    shared_cache_root,
+                    dyld_search_paths,
+                    main_executable_path,
+                    actual_path,
+                    dylib,
+                    &rpaths,
+                )
+            }
+            BinaryFormat::Elf => get_potential_elf_paths(actual_path, dylib, &search_paths),
+        };
+
+        let mut node = None;
+        for path in candidate_paths {
+            if path.exists() {
+                let current_version = match required_compatibility_version {
+                    Some(_) => cache.get_or_parse(&path)?.current_version,
+                    None => None,
+                };
+
+                let (mut child, updated_visited) = build_dependency_tree(
+                    shared_cache_root,
+                    dyld_search_paths,
+                    main_executable_path,
+                    &path,
+                    dylib,
+                    depth + 1,
+                    max_depth,
+                    &visited,
+                    ignore_prefixes,
+                    exclude_all_duplicates,
+                    include_system_dependencies,
+                    cache,
+                )?;
+                visited = updated_visited;
+
+                child.current_version = current_version;
+                child.required_compatibility_version = required_compatibility_version;
+                child.is_system = is_system;
+                node = Some(child);
+                break;
+            }
+        }
+
+        children.push(node.unwrap_or_else(|| TreeNode {
+            name: dylib.to_string(),
+            resolved_path: None,
+            current_version: None,
+            required_compatibility_version,
+            is_system,
+            not_found: true,
+            duplicate: false,
+            children: vec![],
+        }));
+    }
+
+    Ok((
+        TreeNode {
+            name: canonical_path.to_string(),
+            resolved_path: Some(actual_path.to_string_lossy().to_string()),
+            current_version: None,
+            required_compatibility_version: None,
+            is_system: false,
+            not_found: false,
+            duplicate: false,
+            children,
+        },
+        visited,
+    ))
+}
+
 fn main() -> Result<(), error::Error> {
     unsafe {
         // https://github.com/rust-lang/rust/issues/46016#issuecomment-428106774
@@ -247,30 +731,277 @@ fn main() -> Result<(), error::Error> {
 
     if args.shared_cache_path == None && args.include_system_dependencies {
         let buffer = &fs::read(&args.binary)?;
-        let initial_binary = load_binary(&args.binary, buffer)?;
-
-        for lc in initial_binary.load_commands {
-            if let goblin::mach::load_command::CommandVariant::BuildVersion(version) = lc.command {
-                if version.platform != goblin::mach::load_command::PLATFORM_MACOS {
-                    eprintln!("warning: binary is not built for macOS but --shared-cache-path is not specified, so system dependencies may be invalid.");
+        // The dyld shared cache only exists on macOS, so this warning is meaningless for other
+        // formats; skip it rather than rejecting ELF/etc. binaries outright.
+        if matches!(Object::parse(buffer)?, Object::Mach(_)) {
+            let initial_binary = load_macho(&args.binary, buffer)?;
+
+            for lc in initial_binary.load_commands {
+                if let goblin::mach::load_command::CommandVariant::BuildVersion(version) = lc.command {
+                    if version.platform != goblin::mach::load_command::PLATFORM_MACOS {
+                        eprintln!("warning: binary is not built for macOS but --shared-cache-path is not specified, so system dependencies may be invalid.");
+                    }
+                    break;
                 }
-                break;
             }
         }
     }
 
     let visited = HashSet::new();
-    print_dylib_paths(
-        &extracted_cache_path,
-        &args.binary,
-        args.binary.to_str().unwrap(),
-        0,
-        args.depth,
-        &visited,
-        &args.ignore_prefixes,
-        args.exclude_all_duplicates,
-        args.include_system_dependencies,
-        args.verbose,
-    )?;
+    let mut cache = ParseCache::new();
+    let dyld_search_paths = DyldSearchPaths::from_env();
+
+    match args.format {
+        output::OutputFormat::Text => {
+            print_dylib_paths(
+                &extracted_cache_path,
+                &dyld_search_paths,
+                &args.binary,
+                &args.binary,
+                args.binary.to_str().unwrap(),
+                0,
+                args.depth,
+                &visited,
+                &args.ignore_prefixes,
+                args.exclude_all_duplicates,
+                args.include_system_dependencies,
+                args.verbose,
+                &mut cache,
+            )?;
+        }
+        format @ (output::OutputFormat::Json | output::OutputFormat::Dot) => {
+            let (tree, _) = build_dependency_tree(
+                &extracted_cache_path,
+                &dyld_search_paths,
+                &args.binary,
+                &args.binary,
+                args.binary.to_str().unwrap(),
+                0,
+                args.depth,
+                &visited,
+                &args.ignore_prefixes,
+                args.exclude_all_duplicates,
+                args.include_system_dependencies,
+                &mut cache,
+            )?;
+
+            match format {
+                output::OutputFormat::Json => println!("{}", output::tree_to_json(&tree)),
+                output::OutputFormat::Dot => println!("{}", output::tree_to_dot(&tree)),
+                output::OutputFormat::Text => unreachable!(),
+            }
+        }
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod cache_key_tests {
+    use super::*;
+
+    #[test]
+    fn falls_back_to_the_given_path_when_it_does_not_exist() {
+        let missing = Path::new("/nonexistent/path/that/should/not/be/on/disk/libfoo.dylib");
+        assert_eq!(cache_key(missing), missing.to_path_buf());
+    }
+
+    #[test]
+    fn resolves_a_symlink_to_the_same_key_as_its_target() {
+        let dir = std::env::temp_dir().join(format!(
+            "dylibtree-cache-key-test-{:?}",
+            std::thread::current().id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("real.dylib");
+        let link = dir.join("alias.dylib");
+        fs::write(&target, b"").unwrap();
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        // A dependency resolved once via `alias.dylib` and again via `real.dylib` (or a different
+        // relative spelling of either) must land on the same cache entry rather than being parsed
+        // twice.
+        assert_eq!(cache_key(&link), cache_key(&target));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod resolve_at_path_tests {
+    use super::*;
+
+    #[test]
+    fn executable_path_resolves_against_the_main_executable_dir() {
+        let main_executable_dir = Path::new("/Applications/App.app/Contents/MacOS");
+        let loader_dir = Path::new("/Applications/App.app/Contents/Frameworks");
+
+        let resolved = resolve_at_path(
+            "@executable_path/../Frameworks/Foo.framework/Foo",
+            main_executable_dir,
+            loader_dir,
+        );
+
+        assert_eq!(
+            resolved,
+            Some(PathBuf::from(
+                "/Applications/App.app/Contents/MacOS/../Frameworks/Foo.framework/Foo"
+            ))
+        );
+    }
+
+    #[test]
+    fn loader_path_resolves_against_the_loading_images_dir_not_the_main_executable() {
+        let main_executable_dir = Path::new("/Applications/App.app/Contents/MacOS");
+        let loader_dir = Path::new("/Applications/App.app/Contents/Frameworks/Bar.framework");
+
+        let resolved = resolve_at_path(
+            "@loader_path/Foo.dylib",
+            main_executable_dir,
+            loader_dir,
+        );
+
+        assert_eq!(
+            resolved,
+            Some(PathBuf::from(
+                "/Applications/App.app/Contents/Frameworks/Bar.framework/Foo.dylib"
+            ))
+        );
+    }
+
+    #[test]
+    fn non_at_prefixed_values_are_not_resolved() {
+        let main_executable_dir = Path::new("/Applications/App.app/Contents/MacOS");
+        let loader_dir = Path::new("/Applications/App.app/Contents/Frameworks");
+
+        assert_eq!(
+            resolve_at_path("/usr/lib/libfoo.dylib", main_executable_dir, loader_dir),
+            None
+        );
+        assert_eq!(
+            resolve_at_path("@rpath/Foo.framework/Foo", main_executable_dir, loader_dir),
+            None
+        );
+    }
+}
+
+#[cfg(test)]
+mod dyld_env_tests {
+    use super::*;
+
+    // Env vars are process-global, so give each test its own names to stay independent of
+    // whatever else `cargo test`'s default multi-threaded runner has set or unset concurrently.
+
+    #[test]
+    fn env_path_list_splits_colon_separated_entries_and_drops_empties() {
+        let name = "DYLIBTREE_TEST_ENV_PATH_LIST_SPLIT";
+        std::env::set_var(name, "/a/b:/c/d::/e/f");
+
+        assert_eq!(
+            env_path_list(name),
+            vec![PathBuf::from("/a/b"), PathBuf::from("/c/d"), PathBuf::from("/e/f")]
+        );
+
+        std::env::remove_var(name);
+    }
+
+    #[test]
+    fn env_path_list_is_empty_when_var_is_unset() {
+        let name = "DYLIBTREE_TEST_ENV_PATH_LIST_UNSET";
+        std::env::remove_var(name);
+
+        assert!(env_path_list(name).is_empty());
+    }
+
+    #[test]
+    fn from_env_reads_all_four_dyld_variables_independently() {
+        std::env::set_var("DYLD_FRAMEWORK_PATH", "/framework");
+        std::env::set_var("DYLD_LIBRARY_PATH", "/library");
+        std::env::set_var("DYLD_FALLBACK_FRAMEWORK_PATH", "/fallback-framework");
+        std::env::set_var("DYLD_FALLBACK_LIBRARY_PATH", "/fallback-library");
+
+        let paths = DyldSearchPaths::from_env();
+
+        assert_eq!(paths.framework_path, vec![PathBuf::from("/framework")]);
+        assert_eq!(paths.library_path, vec![PathBuf::from("/library")]);
+        assert_eq!(
+            paths.fallback_framework_path,
+            vec![PathBuf::from("/fallback-framework")]
+        );
+        assert_eq!(
+            paths.fallback_library_path,
+            vec![PathBuf::from("/fallback-library")]
+        );
+
+        std::env::remove_var("DYLD_FRAMEWORK_PATH");
+        std::env::remove_var("DYLD_LIBRARY_PATH");
+        std::env::remove_var("DYLD_FALLBACK_FRAMEWORK_PATH");
+        std::env::remove_var("DYLD_FALLBACK_LIBRARY_PATH");
+    }
+}
+
+#[cfg(test)]
+mod elf_backend_tests {
+    use super::*;
+
+    #[test]
+    fn expand_elf_tokens_substitutes_origin_lib_and_platform() {
+        let loader_dir = Path::new("/opt/app/bin");
+        assert_eq!(
+            expand_elf_tokens("$ORIGIN/../lib", loader_dir),
+            "/opt/app/bin/../lib"
+        );
+        assert_eq!(
+            expand_elf_tokens("${ORIGIN}/../lib", loader_dir),
+            "/opt/app/bin/../lib"
+        );
+        assert_eq!(expand_elf_tokens("/usr/$LIB", loader_dir), "/usr/lib");
+        assert_eq!(
+            expand_elf_tokens("/usr/lib/$PLATFORM", loader_dir),
+            format!("/usr/lib/{}", std::env::consts::ARCH)
+        );
+    }
+
+    #[test]
+    fn get_potential_elf_paths_searches_rpath_before_needed_and_runpath_after() {
+        let actual_path = Path::new("/opt/app/bin/app");
+        let search_paths = vec![
+            SearchPath {
+                raw: "/opt/app/rpath-lib".to_string(),
+                searched_after_needed: false,
+            },
+            SearchPath {
+                raw: "/opt/app/runpath-lib".to_string(),
+                searched_after_needed: true,
+            },
+        ];
+
+        let paths = get_potential_elf_paths(actual_path, "libfoo.so", &search_paths);
+
+        let rpath_index = paths
+            .iter()
+            .position(|p| p == &PathBuf::from("/opt/app/rpath-lib/libfoo.so"))
+            .expect("rpath candidate missing");
+        let bare_index = paths
+            .iter()
+            .position(|p| p == &PathBuf::from("libfoo.so"))
+            .expect("bare lib name candidate missing");
+        let runpath_index = paths
+            .iter()
+            .position(|p| p == &PathBuf::from("/opt/app/runpath-lib/libfoo.so"))
+            .expect("runpath candidate missing");
+
+        assert!(rpath_index < bare_index);
+        assert!(bare_index < runpath_index);
+        assert!(paths.contains(&PathBuf::from("/usr/lib/libfoo.so")));
+    }
+
+    #[test]
+    fn is_system_dependency_uses_elf_prefixes_for_elf_binaries() {
+        assert!(is_system_dependency(BinaryFormat::Elf, "/lib64/libc.so.6"));
+        assert!(is_system_dependency(BinaryFormat::Elf, "linux-vdso.so.1"));
+        assert!(!is_system_dependency(BinaryFormat::Elf, "/opt/app/libfoo.so"));
+        assert!(!is_system_dependency(BinaryFormat::MachO, "/lib64/libc.so.6"));
+    }
+}
@@ -0,0 +1,248 @@
+/// Selects how `main` renders the dependency walk. `cli` parses `--format` into this; `Text`
+/// (the existing indented `println!` output) stays the default.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Dot,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "dot" => Ok(OutputFormat::Dot),
+            other => Err(format!("unknown output format: {}", other)),
+        }
+    }
+}
+
+/// One visited edge of the dependency walk, together with the subtree rooted at its target. This
+/// mirrors the text traversal exactly (including a childless marker entry for each duplicate or
+/// not-found dependency), so [`tree_to_json`] can serialize the full tree; [`tree_to_dot`]
+/// flattens it into a deduped DAG instead.
+pub struct TreeNode {
+    pub name: String,
+    pub resolved_path: Option<String>,
+    pub current_version: Option<u32>,
+    pub required_compatibility_version: Option<u32>,
+    pub is_system: bool,
+    pub not_found: bool,
+    pub duplicate: bool,
+    pub children: Vec<TreeNode>,
+}
+
+fn escape_json(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn write_json(node: &TreeNode, out: &mut String) {
+    out.push('{');
+    out.push_str(&format!("\"name\":\"{}\"", escape_json(&node.name)));
+    out.push_str(&format!(
+        ",\"resolved_path\":{}",
+        node.resolved_path
+            .as_ref()
+            .map(|p| format!("\"{}\"", escape_json(p)))
+            .unwrap_or_else(|| "null".to_string())
+    ));
+    out.push_str(&format!(
+        ",\"current_version\":{}",
+        node.current_version.map(|v| v.to_string()).unwrap_or_else(|| "null".to_string())
+    ));
+    out.push_str(&format!(
+        ",\"required_compatibility_version\":{}",
+        node.required_compatibility_version
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| "null".to_string())
+    ));
+    out.push_str(&format!(",\"is_system\":{}", node.is_system));
+    out.push_str(&format!(",\"not_found\":{}", node.not_found));
+    out.push_str(&format!(",\"duplicate\":{}", node.duplicate));
+    out.push_str(",\"children\":[");
+    for (index, child) in node.children.iter().enumerate() {
+        if index > 0 {
+            out.push(',');
+        }
+        write_json(child, out);
+    }
+    out.push_str("]}");
+}
+
+pub fn tree_to_json(root: &TreeNode) -> String {
+    let mut out = String::new();
+    write_json(root, &mut out);
+    out
+}
+
+fn escape_dot(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn collect_dot<'a>(
+    node: &'a TreeNode,
+    nodes: &mut std::collections::HashMap<String, &'a TreeNode>,
+    edges: &mut std::collections::HashSet<(String, String)>,
+    parent: Option<&str>,
+) {
+    nodes.entry(node.name.clone()).or_insert(node);
+
+    if let Some(parent) = parent {
+        edges.insert((parent.to_string(), node.name.clone()));
+    }
+
+    // A duplicate marker means this subtree was already walked under its first occurrence, so
+    // don't re-walk its (empty, by construction) children here -- the node itself is enough to
+    // own the edge into it.
+    if node.duplicate {
+        return;
+    }
+
+    for child in &node.children {
+        collect_dot(child, nodes, edges, Some(&node.name));
+    }
+}
+
+pub fn tree_to_dot(root: &TreeNode) -> String {
+    let mut nodes = std::collections::HashMap::new();
+    let mut edges = std::collections::HashSet::new();
+    collect_dot(root, &mut nodes, &mut edges, None);
+
+    let mut out = String::from("digraph dylibtree {\n");
+
+    let mut names: Vec<&String> = nodes.keys().collect();
+    names.sort();
+    for name in names {
+        let node = nodes[name];
+        let mut attrs = vec![];
+        if node.not_found {
+            attrs.push("color=red".to_string());
+            attrs.push("label=\"".to_string() + &escape_dot(name) + " (not found)\"");
+        }
+        if node.is_system {
+            attrs.push("style=dashed".to_string());
+        }
+
+        if attrs.is_empty() {
+            out.push_str(&format!("  \"{}\";\n", escape_dot(name)));
+        } else {
+            out.push_str(&format!("  \"{}\" [{}];\n", escape_dot(name), attrs.join(", ")));
+        }
+    }
+
+    let mut sorted_edges: Vec<&(String, String)> = edges.iter().collect();
+    sorted_edges.sort();
+    for (from, to) in sorted_edges {
+        out.push_str(&format!("  \"{}\" -> \"{}\";\n", escape_dot(from), escape_dot(to)));
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn leaf(name: &str) -> TreeNode {
+        TreeNode {
+            name: name.to_string(),
+            resolved_path: None,
+            current_version: None,
+            required_compatibility_version: None,
+            is_system: false,
+            not_found: false,
+            duplicate: false,
+            children: vec![],
+        }
+    }
+
+    #[test]
+    fn output_format_from_str_parses_known_values_only() {
+        assert!(OutputFormat::from_str("text") == Ok(OutputFormat::Text));
+        assert!(OutputFormat::from_str("json") == Ok(OutputFormat::Json));
+        assert!(OutputFormat::from_str("dot") == Ok(OutputFormat::Dot));
+        assert!(OutputFormat::from_str("yaml").is_err());
+    }
+
+    #[test]
+    fn tree_to_json_escapes_strings_and_nests_children() {
+        let mut not_found_child = leaf("libmissing.so");
+        not_found_child.not_found = true;
+
+        let mut duplicate_child = leaf("libshared.so");
+        duplicate_child.duplicate = true;
+
+        let root = TreeNode {
+            name: "app \"weird\"\\name".to_string(),
+            resolved_path: Some("/opt/app/bin/app".to_string()),
+            current_version: Some(0x0001_0203),
+            required_compatibility_version: None,
+            is_system: false,
+            not_found: false,
+            duplicate: false,
+            children: vec![not_found_child, duplicate_child],
+        };
+
+        let json = tree_to_json(&root);
+
+        assert!(json.contains("\"name\":\"app \\\"weird\\\"\\\\name\""));
+        assert!(json.contains("\"resolved_path\":\"/opt/app/bin/app\""));
+        assert!(json.contains("\"current_version\":66051"));
+        assert!(json.contains("\"name\":\"libmissing.so\",\"resolved_path\":null"));
+        assert!(json.contains("\"not_found\":true"));
+        assert!(json.contains("\"duplicate\":true"));
+    }
+
+    #[test]
+    fn tree_to_dot_dedups_shared_dependencies_into_one_node() {
+        let shared_dep = leaf("libshared.so");
+        let mut shared_dep_again = leaf("libshared.so");
+        shared_dep_again.duplicate = true;
+
+        let mut not_found = leaf("libmissing.so");
+        not_found.not_found = true;
+
+        let mut child_a = leaf("liba.so");
+        child_a.children = vec![shared_dep];
+
+        let mut child_b = leaf("libb.so");
+        child_b.children = vec![shared_dep_again, not_found];
+
+        let root = TreeNode {
+            name: "app".to_string(),
+            resolved_path: Some("/opt/app/bin/app".to_string()),
+            current_version: None,
+            required_compatibility_version: None,
+            is_system: false,
+            not_found: false,
+            duplicate: false,
+            children: vec![child_a, child_b],
+        };
+
+        let dot = tree_to_dot(&root);
+
+        // One node declaration plus one inbound edge from each of "liba.so" and "libb.so" --
+        // "libshared.so" is deduped into a single node even though it's reachable (and marked
+        // duplicate the second time) via both parents.
+        assert_eq!(dot.matches("\"libshared.so\"").count(), 3);
+        assert!(dot.contains("\"app\" -> \"liba.so\";"));
+        assert!(dot.contains("\"app\" -> \"libb.so\";"));
+        assert!(dot.contains("\"liba.so\" -> \"libshared.so\";"));
+        assert!(dot.contains("\"libb.so\" -> \"libshared.so\";"));
+        assert!(dot.contains("\"libmissing.so\" [color=red, label=\"libmissing.so (not found)\""));
+    }
+}